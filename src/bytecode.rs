@@ -0,0 +1,446 @@
+use super::class::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Nop,
+    AconstNull,
+    IConst(i32),
+    LConst(i64),
+    FConst(f32),
+    DConst(f64),
+    Bipush(i8),
+    Sipush(i16),
+    Ldc 		{ index: u8 },
+    LdcW 		{ index: u16 },
+    Ldc2W 		{ index: u16 },
+
+    ILoad 		{ index: u16 },
+    LLoad 		{ index: u16 },
+    FLoad 		{ index: u16 },
+    DLoad 		{ index: u16 },
+    ALoad 		{ index: u16 },
+    IALoad, LALoad, FALoad, DALoad, AALoad, BALoad, CALoad, SALoad,
+
+    IStore 		{ index: u16 },
+    LStore 		{ index: u16 },
+    FStore 		{ index: u16 },
+    DStore 		{ index: u16 },
+    AStore 		{ index: u16 },
+    IAStore, LAStore, FAStore, DAStore, AAStore, BAStore, CAStore, SAStore,
+
+    Pop, Pop2,
+    Dup, DupX1, DupX2, Dup2, Dup2X1, Dup2X2, Swap,
+
+    Iadd, Ladd, Fadd, Dadd,
+    Isub, Lsub, Fsub, Dsub,
+    Imul, Lmul, Fmul, Dmul,
+    Idiv, Ldiv, Fdiv, Ddiv,
+    Irem, Lrem, Frem, Drem,
+    Ineg, Lneg, Fneg, Dneg,
+    Ishl, Lshl, Ishr, Lshr, Iushr, Lushr,
+    Iand, Land, Ior, Lor, Ixor, Lxor,
+
+    IInc 		{ index: u16, value: i16 },
+
+    I2l, I2f, I2d, L2i, L2f, L2d, F2i, F2l, F2d, D2i, D2l, D2f, I2b, I2c, I2s,
+    Lcmp, Fcmpl, Fcmpg, Dcmpl, Dcmpg,
+
+    IfEq 		{ offset: i16 },
+    IfNe 		{ offset: i16 },
+    IfLt 		{ offset: i16 },
+    IfGe 		{ offset: i16 },
+    IfGt 		{ offset: i16 },
+    IfLe 		{ offset: i16 },
+    IfICmpEq 		{ offset: i16 },
+    IfICmpNe 		{ offset: i16 },
+    IfICmpLt 		{ offset: i16 },
+    IfICmpGe 		{ offset: i16 },
+    IfICmpGt 		{ offset: i16 },
+    IfICmpLe 		{ offset: i16 },
+    IfACmpEq 		{ offset: i16 },
+    IfACmpNe 		{ offset: i16 },
+    Goto 		{ offset: i16 },
+    Jsr 		{ offset: i16 },
+    Ret 		{ index: u16 },
+
+    TableSwitch 	{ default: i32, low: i32, high: i32, offsets: Vec<i32> },
+    LookupSwitch 	{ default: i32, pairs: Vec<(i32, i32)> },
+
+    IReturn, LReturn, FReturn, DReturn, AReturn, Return,
+
+    GetStatic 		{ index: u16 },
+    PutStatic 		{ index: u16 },
+    GetField 		{ index: u16 },
+    PutField 		{ index: u16 },
+    InvokeVirtual 	{ index: u16 },
+    InvokeSpecial 	{ index: u16 },
+    InvokeStatic 	{ index: u16 },
+    InvokeInterface 	{ index: u16, count: u8 },
+    InvokeDynamic 	{ index: u16 },
+
+    New 		{ index: u16 },
+    NewArray 		{ atype: u8 },
+    ANewArray 		{ index: u16 },
+    ArrayLength,
+    AThrow,
+    CheckCast 		{ index: u16 },
+    InstanceOf 		{ index: u16 },
+    MonitorEnter,
+    MonitorExit,
+    MultiANewArray 	{ index: u16, dimensions: u8 },
+
+    IfNull 		{ offset: i16 },
+    IfNonNull 		{ offset: i16 },
+    GotoW 		{ offset: i32 },
+    JsrW 		{ offset: i32 }
+}
+
+struct Cursor<'a> {
+    code: 	&'a [u8],
+    pos: 	usize
+}
+
+impl<'a> Cursor<'a> {
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        let byte = *self.code.get(self.pos).ok_or(Error::TruncatedCode)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn i8(&mut self) -> Result<i8, Error> {
+        Ok(self.u8()? as i8)
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let hi = self.u8()? as u16;
+        let lo = self.u8()? as u16;
+        Ok((hi << 8) | lo)
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let hi = self.u16()? as u32;
+        let lo = self.u16()? as u32;
+        Ok((hi << 16) | lo)
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        Ok(self.u32()? as i32)
+    }
+
+    // tableswitch/lookupswitch pad with zero bytes up to the next address
+    // that is a multiple of four, counted from the start of the method.
+    fn align_to_four(&mut self) -> Result<(), Error> {
+        while !self.pos.is_multiple_of(4) {
+            self.u8()?;
+        }
+        Ok(())
+    }
+
+    fn remaining(&self) -> usize {
+        self.code.len() - self.pos
+    }
+
+}
+
+pub fn disassemble(code: &[u8]) -> Result<Vec<(u32, Instruction)>, Error> {
+    let mut cursor = Cursor { code, pos: 0 };
+    let mut instructions = Vec::new();
+
+    while cursor.pos < code.len() {
+        let offset = cursor.pos as u32;
+        let opcode = cursor.u8()?;
+        let instruction = decode(&mut cursor, opcode, false)?;
+        instructions.push((offset, instruction));
+    }
+
+    Ok(instructions)
+}
+
+fn decode(cursor: &mut Cursor, opcode: u8, wide: bool) -> Result<Instruction, Error> {
+    Ok(match opcode {
+        0x00 => Instruction::Nop,
+        0x01 => Instruction::AconstNull,
+        0x02 => Instruction::IConst(-1),
+        0x03 => Instruction::IConst(0),
+        0x04 => Instruction::IConst(1),
+        0x05 => Instruction::IConst(2),
+        0x06 => Instruction::IConst(3),
+        0x07 => Instruction::IConst(4),
+        0x08 => Instruction::IConst(5),
+        0x09 => Instruction::LConst(0),
+        0x0a => Instruction::LConst(1),
+        0x0b => Instruction::FConst(0.0),
+        0x0c => Instruction::FConst(1.0),
+        0x0d => Instruction::FConst(2.0),
+        0x0e => Instruction::DConst(0.0),
+        0x0f => Instruction::DConst(1.0),
+        0x10 => Instruction::Bipush(cursor.i8()?),
+        0x11 => Instruction::Sipush(cursor.i16()?),
+        0x12 => Instruction::Ldc { index: cursor.u8()? },
+        0x13 => Instruction::LdcW { index: cursor.u16()? },
+        0x14 => Instruction::Ldc2W { index: cursor.u16()? },
+
+        0x15 => Instruction::ILoad { index: load_index(cursor, wide)? },
+        0x16 => Instruction::LLoad { index: load_index(cursor, wide)? },
+        0x17 => Instruction::FLoad { index: load_index(cursor, wide)? },
+        0x18 => Instruction::DLoad { index: load_index(cursor, wide)? },
+        0x19 => Instruction::ALoad { index: load_index(cursor, wide)? },
+        0x1a => Instruction::ILoad { index: 0 },
+        0x1b => Instruction::ILoad { index: 1 },
+        0x1c => Instruction::ILoad { index: 2 },
+        0x1d => Instruction::ILoad { index: 3 },
+        0x1e => Instruction::LLoad { index: 0 },
+        0x1f => Instruction::LLoad { index: 1 },
+        0x20 => Instruction::LLoad { index: 2 },
+        0x21 => Instruction::LLoad { index: 3 },
+        0x22 => Instruction::FLoad { index: 0 },
+        0x23 => Instruction::FLoad { index: 1 },
+        0x24 => Instruction::FLoad { index: 2 },
+        0x25 => Instruction::FLoad { index: 3 },
+        0x26 => Instruction::DLoad { index: 0 },
+        0x27 => Instruction::DLoad { index: 1 },
+        0x28 => Instruction::DLoad { index: 2 },
+        0x29 => Instruction::DLoad { index: 3 },
+        0x2a => Instruction::ALoad { index: 0 },
+        0x2b => Instruction::ALoad { index: 1 },
+        0x2c => Instruction::ALoad { index: 2 },
+        0x2d => Instruction::ALoad { index: 3 },
+        0x2e => Instruction::IALoad,
+        0x2f => Instruction::LALoad,
+        0x30 => Instruction::FALoad,
+        0x31 => Instruction::DALoad,
+        0x32 => Instruction::AALoad,
+        0x33 => Instruction::BALoad,
+        0x34 => Instruction::CALoad,
+        0x35 => Instruction::SALoad,
+
+        0x36 => Instruction::IStore { index: load_index(cursor, wide)? },
+        0x37 => Instruction::LStore { index: load_index(cursor, wide)? },
+        0x38 => Instruction::FStore { index: load_index(cursor, wide)? },
+        0x39 => Instruction::DStore { index: load_index(cursor, wide)? },
+        0x3a => Instruction::AStore { index: load_index(cursor, wide)? },
+        0x3b => Instruction::IStore { index: 0 },
+        0x3c => Instruction::IStore { index: 1 },
+        0x3d => Instruction::IStore { index: 2 },
+        0x3e => Instruction::IStore { index: 3 },
+        0x3f => Instruction::LStore { index: 0 },
+        0x40 => Instruction::LStore { index: 1 },
+        0x41 => Instruction::LStore { index: 2 },
+        0x42 => Instruction::LStore { index: 3 },
+        0x43 => Instruction::FStore { index: 0 },
+        0x44 => Instruction::FStore { index: 1 },
+        0x45 => Instruction::FStore { index: 2 },
+        0x46 => Instruction::FStore { index: 3 },
+        0x47 => Instruction::DStore { index: 0 },
+        0x48 => Instruction::DStore { index: 1 },
+        0x49 => Instruction::DStore { index: 2 },
+        0x4a => Instruction::DStore { index: 3 },
+        0x4b => Instruction::AStore { index: 0 },
+        0x4c => Instruction::AStore { index: 1 },
+        0x4d => Instruction::AStore { index: 2 },
+        0x4e => Instruction::AStore { index: 3 },
+        0x4f => Instruction::IAStore,
+        0x50 => Instruction::LAStore,
+        0x51 => Instruction::FAStore,
+        0x52 => Instruction::DAStore,
+        0x53 => Instruction::AAStore,
+        0x54 => Instruction::BAStore,
+        0x55 => Instruction::CAStore,
+        0x56 => Instruction::SAStore,
+
+        0x57 => Instruction::Pop,
+        0x58 => Instruction::Pop2,
+        0x59 => Instruction::Dup,
+        0x5a => Instruction::DupX1,
+        0x5b => Instruction::DupX2,
+        0x5c => Instruction::Dup2,
+        0x5d => Instruction::Dup2X1,
+        0x5e => Instruction::Dup2X2,
+        0x5f => Instruction::Swap,
+
+        0x60 => Instruction::Iadd,
+        0x61 => Instruction::Ladd,
+        0x62 => Instruction::Fadd,
+        0x63 => Instruction::Dadd,
+        0x64 => Instruction::Isub,
+        0x65 => Instruction::Lsub,
+        0x66 => Instruction::Fsub,
+        0x67 => Instruction::Dsub,
+        0x68 => Instruction::Imul,
+        0x69 => Instruction::Lmul,
+        0x6a => Instruction::Fmul,
+        0x6b => Instruction::Dmul,
+        0x6c => Instruction::Idiv,
+        0x6d => Instruction::Ldiv,
+        0x6e => Instruction::Fdiv,
+        0x6f => Instruction::Ddiv,
+        0x70 => Instruction::Irem,
+        0x71 => Instruction::Lrem,
+        0x72 => Instruction::Frem,
+        0x73 => Instruction::Drem,
+        0x74 => Instruction::Ineg,
+        0x75 => Instruction::Lneg,
+        0x76 => Instruction::Fneg,
+        0x77 => Instruction::Dneg,
+        0x78 => Instruction::Ishl,
+        0x79 => Instruction::Lshl,
+        0x7a => Instruction::Ishr,
+        0x7b => Instruction::Lshr,
+        0x7c => Instruction::Iushr,
+        0x7d => Instruction::Lushr,
+        0x7e => Instruction::Iand,
+        0x7f => Instruction::Land,
+        0x80 => Instruction::Ior,
+        0x81 => Instruction::Lor,
+        0x82 => Instruction::Ixor,
+        0x83 => Instruction::Lxor,
+
+        0x84 => {
+            if wide {
+                Instruction::IInc { index: cursor.u16()?, value: cursor.i16()? }
+            } else {
+                Instruction::IInc { index: cursor.u8()? as u16, value: cursor.i8()? as i16 }
+            }
+        },
+
+        0x85 => Instruction::I2l,
+        0x86 => Instruction::I2f,
+        0x87 => Instruction::I2d,
+        0x88 => Instruction::L2i,
+        0x89 => Instruction::L2f,
+        0x8a => Instruction::L2d,
+        0x8b => Instruction::F2i,
+        0x8c => Instruction::F2l,
+        0x8d => Instruction::F2d,
+        0x8e => Instruction::D2i,
+        0x8f => Instruction::D2l,
+        0x90 => Instruction::D2f,
+        0x91 => Instruction::I2b,
+        0x92 => Instruction::I2c,
+        0x93 => Instruction::I2s,
+        0x94 => Instruction::Lcmp,
+        0x95 => Instruction::Fcmpl,
+        0x96 => Instruction::Fcmpg,
+        0x97 => Instruction::Dcmpl,
+        0x98 => Instruction::Dcmpg,
+
+        0x99 => Instruction::IfEq { offset: cursor.i16()? },
+        0x9a => Instruction::IfNe { offset: cursor.i16()? },
+        0x9b => Instruction::IfLt { offset: cursor.i16()? },
+        0x9c => Instruction::IfGe { offset: cursor.i16()? },
+        0x9d => Instruction::IfGt { offset: cursor.i16()? },
+        0x9e => Instruction::IfLe { offset: cursor.i16()? },
+        0x9f => Instruction::IfICmpEq { offset: cursor.i16()? },
+        0xa0 => Instruction::IfICmpNe { offset: cursor.i16()? },
+        0xa1 => Instruction::IfICmpLt { offset: cursor.i16()? },
+        0xa2 => Instruction::IfICmpGe { offset: cursor.i16()? },
+        0xa3 => Instruction::IfICmpGt { offset: cursor.i16()? },
+        0xa4 => Instruction::IfICmpLe { offset: cursor.i16()? },
+        0xa5 => Instruction::IfACmpEq { offset: cursor.i16()? },
+        0xa6 => Instruction::IfACmpNe { offset: cursor.i16()? },
+        0xa7 => Instruction::Goto { offset: cursor.i16()? },
+        0xa8 => Instruction::Jsr { offset: cursor.i16()? },
+        0xa9 => Instruction::Ret { index: load_index(cursor, wide)? },
+
+        0xaa => {
+            cursor.align_to_four()?;
+            let default = cursor.i32()?;
+            let low = cursor.i32()?;
+            let high = cursor.i32()?;
+            if low > high {
+                return Err(Error::BadSwitch);
+            }
+            // Widen to i64 so a full-range low/high pair can't overflow the
+            // subtraction, then bound it by what the remaining bytes could
+            // actually hold so a huge count can't blow up Vec::with_capacity.
+            let count = (high as i64 - low as i64 + 1) as usize;
+            if count > cursor.remaining() / 4 {
+                return Err(Error::BadSwitch);
+            }
+            let mut offsets = Vec::with_capacity(count);
+            for _ in 0..count {
+                offsets.push(cursor.i32()?);
+            }
+            Instruction::TableSwitch { default, low, high, offsets }
+        },
+
+        0xab => {
+            cursor.align_to_four()?;
+            let default = cursor.i32()?;
+            let npairs = cursor.i32()?;
+            if npairs < 0 || npairs as usize > cursor.remaining() / 8 {
+                return Err(Error::BadSwitch);
+            }
+            let npairs = npairs as usize;
+            let mut pairs = Vec::with_capacity(npairs);
+            for _ in 0..npairs {
+                let value = cursor.i32()?;
+                let offset = cursor.i32()?;
+                pairs.push((value, offset));
+            }
+            Instruction::LookupSwitch { default, pairs }
+        },
+
+        0xac => Instruction::IReturn,
+        0xad => Instruction::LReturn,
+        0xae => Instruction::FReturn,
+        0xaf => Instruction::DReturn,
+        0xb0 => Instruction::AReturn,
+        0xb1 => Instruction::Return,
+
+        0xb2 => Instruction::GetStatic { index: cursor.u16()? },
+        0xb3 => Instruction::PutStatic { index: cursor.u16()? },
+        0xb4 => Instruction::GetField { index: cursor.u16()? },
+        0xb5 => Instruction::PutField { index: cursor.u16()? },
+        0xb6 => Instruction::InvokeVirtual { index: cursor.u16()? },
+        0xb7 => Instruction::InvokeSpecial { index: cursor.u16()? },
+        0xb8 => Instruction::InvokeStatic { index: cursor.u16()? },
+        0xb9 => {
+            let index = cursor.u16()?;
+            let count = cursor.u8()?;
+            let _reserved = cursor.u8()?;
+            Instruction::InvokeInterface { index, count }
+        },
+        0xba => {
+            let index = cursor.u16()?;
+            let _reserved = cursor.u16()?;
+            Instruction::InvokeDynamic { index }
+        },
+
+        0xbb => Instruction::New { index: cursor.u16()? },
+        0xbc => Instruction::NewArray { atype: cursor.u8()? },
+        0xbd => Instruction::ANewArray { index: cursor.u16()? },
+        0xbe => Instruction::ArrayLength,
+        0xbf => Instruction::AThrow,
+        0xc0 => Instruction::CheckCast { index: cursor.u16()? },
+        0xc1 => Instruction::InstanceOf { index: cursor.u16()? },
+        0xc2 => Instruction::MonitorEnter,
+        0xc3 => Instruction::MonitorExit,
+
+        0xc4 => {
+            let wide_opcode = cursor.u8()?;
+            decode(cursor, wide_opcode, true)?
+        },
+
+        0xc5 => Instruction::MultiANewArray { index: cursor.u16()?, dimensions: cursor.u8()? },
+        0xc6 => Instruction::IfNull { offset: cursor.i16()? },
+        0xc7 => Instruction::IfNonNull { offset: cursor.i16()? },
+        0xc8 => Instruction::GotoW { offset: cursor.i32()? },
+        0xc9 => Instruction::JsrW { offset: cursor.i32()? },
+
+        _ => return Err(Error::UnknownOpcode(opcode))
+    })
+}
+
+fn load_index(cursor: &mut Cursor, wide: bool) -> Result<u16, Error> {
+    if wide {
+        cursor.u16()
+    } else {
+        Ok(cursor.u8()? as u16)
+    }
+}
@@ -0,0 +1,32 @@
+use super::stackframe::Value;
+
+#[derive(Debug, Clone)]
+pub struct Object {
+    pub class_name: 	String,
+    pub fields: 	Vec<Value>
+}
+
+pub struct HeapArea {
+    objects: Vec<Object>
+}
+
+impl HeapArea {
+
+    pub fn new() -> HeapArea {
+        HeapArea { objects: Vec::new() }
+    }
+
+    pub fn allocate(&mut self, object: Object) -> usize {
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+
+    pub fn get(&self, reference: usize) -> Option<&Object> {
+        self.objects.get(reference)
+    }
+
+    pub fn get_mut(&mut self, reference: usize) -> Option<&mut Object> {
+        self.objects.get_mut(reference)
+    }
+
+}
@@ -0,0 +1,150 @@
+use super::bytecode::Instruction;
+use super::class::{Class, Error};
+use super::heap_area::HeapArea;
+use super::stackframe::{StackFrame, Value};
+
+pub fn execute(class: &Class, method_name: &str, args: &[Value]) -> Result<Option<Value>, Error> {
+    let method = class.method_code(method_name)?
+        .ok_or_else(|| Error::UnknownMethod(method_name.to_string()))?;
+
+    let mut frame = StackFrame::new(method.max_stack, method.max_locals);
+    for (index, value) in args.iter().enumerate() {
+        frame.set_local(index, *value)?;
+    }
+
+    // The heap only matters once object/array instructions are supported;
+    // for now it exists so frames have somewhere to allocate into later.
+    let mut heap = HeapArea::new();
+    run(class, &method.instructions, &mut frame, &mut heap)
+}
+
+fn run(class: &Class, instructions: &[(u32, Instruction)], frame: &mut StackFrame, _heap: &mut HeapArea) -> Result<Option<Value>, Error> {
+    let mut pc = 0usize;
+
+    loop {
+        if pc >= instructions.len() {
+            return Ok(None);
+        }
+        let (offset, instruction) = &instructions[pc];
+
+        match instruction {
+            Instruction::Nop => { },
+
+            Instruction::IConst(value) => frame.push(Value::Int(*value))?,
+            Instruction::Bipush(value) => frame.push(Value::Int(*value as i32))?,
+            Instruction::Sipush(value) => frame.push(Value::Int(*value as i32))?,
+
+            Instruction::ILoad { index } => {
+                let value = frame.get_local(*index as usize)?;
+                frame.push(value)?;
+            },
+            Instruction::IStore { index } => {
+                let value = frame.pop()?;
+                frame.set_local(*index as usize, value)?;
+            },
+
+            Instruction::Iadd => binary_int(frame, |a, b| Ok(a.wrapping_add(b)))?,
+            Instruction::Isub => binary_int(frame, |a, b| Ok(a.wrapping_sub(b)))?,
+            Instruction::Imul => binary_int(frame, |a, b| Ok(a.wrapping_mul(b)))?,
+            Instruction::Idiv => binary_int(frame, |a, b| a.checked_div(b).ok_or(Error::DivisionByZero))?,
+            Instruction::Irem => binary_int(frame, |a, b| a.checked_rem(b).ok_or(Error::DivisionByZero))?,
+            Instruction::Ineg => {
+                let value = pop_int(frame)?;
+                frame.push(Value::Int(-value))?;
+            },
+
+            Instruction::IInc { index, value } => {
+                let current = get_local_int(frame, *index as usize)?;
+                frame.set_local(*index as usize, Value::Int(current.wrapping_add(*value as i32)))?;
+            },
+
+            Instruction::IfEq { offset: rel } => if pop_int(frame)? == 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+            Instruction::IfNe { offset: rel } => if pop_int(frame)? != 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+            Instruction::IfLt { offset: rel } => if pop_int(frame)? < 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+            Instruction::IfGe { offset: rel } => if pop_int(frame)? >= 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+            Instruction::IfGt { offset: rel } => if pop_int(frame)? > 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+            Instruction::IfLe { offset: rel } => if pop_int(frame)? <= 0 { pc = jump(instructions, *offset, *rel)?; continue; },
+
+            Instruction::IfICmpEq { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a == b { pc = jump(instructions, *offset, *rel)?; continue; } },
+            Instruction::IfICmpNe { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a != b { pc = jump(instructions, *offset, *rel)?; continue; } },
+            Instruction::IfICmpLt { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a < b { pc = jump(instructions, *offset, *rel)?; continue; } },
+            Instruction::IfICmpGe { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a >= b { pc = jump(instructions, *offset, *rel)?; continue; } },
+            Instruction::IfICmpGt { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a > b { pc = jump(instructions, *offset, *rel)?; continue; } },
+            Instruction::IfICmpLe { offset: rel } => { let (a, b) = pop_int_pair(frame)?; if a <= b { pc = jump(instructions, *offset, *rel)?; continue; } },
+
+            Instruction::Goto { offset: rel } => { pc = jump(instructions, *offset, *rel)?; continue; },
+
+            Instruction::InvokeStatic { index } => {
+                // Without the descriptor parser (see `class::read_type`) we
+                // can't yet tell how many arguments to pop, so only
+                // zero-argument static methods in the same class resolve.
+                let name = class.method_name_for_methodref(*index)?.clone();
+                if let Some(value) = execute(class, &name, &[])? {
+                    frame.push(value)?;
+                }
+            },
+
+            Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn => {
+                return Ok(Some(frame.pop()?));
+            },
+            Instruction::Return => return Ok(None),
+
+            other => return Err(Error::UnsupportedInstruction(format!("{:?}", other)))
+        }
+
+        pc += 1;
+    }
+}
+
+fn pop_int(frame: &mut StackFrame) -> Result<i32, Error> {
+    match frame.pop()? {
+        Value::Int(value) => Ok(value),
+        _ => Err(Error::TypeMismatch)
+    }
+}
+
+fn pop_int_pair(frame: &mut StackFrame) -> Result<(i32, i32), Error> {
+    let b = pop_int(frame)?;
+    let a = pop_int(frame)?;
+    Ok((a, b))
+}
+
+fn get_local_int(frame: &StackFrame, index: usize) -> Result<i32, Error> {
+    match frame.get_local(index)? {
+        Value::Int(value) => Ok(value),
+        _ => Err(Error::TypeMismatch)
+    }
+}
+
+fn binary_int<F>(frame: &mut StackFrame, op: F) -> Result<(), Error>
+where F: FnOnce(i32, i32) -> Result<i32, Error> {
+    let (a, b) = pop_int_pair(frame)?;
+    frame.push(Value::Int(op(a, b)?))
+}
+
+fn jump(instructions: &[(u32, Instruction)], current_offset: u32, relative: i16) -> Result<usize, Error> {
+    let target = (current_offset as i64 + relative as i64) as u32;
+    instructions.iter()
+        .position(|(offset, _)| *offset == target)
+        .ok_or(Error::BadJumpTarget(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::class::ClassFile;
+    use std::path::Path;
+
+    #[test]
+    fn return_55_evaluates_to_55() {
+        let path = Path::new("examples/Return55.class");
+        let mut class_file = ClassFile::new(path).unwrap();
+        let class = class_file.read_class().unwrap();
+
+        assert_eq!(execute(&class, "main", &[]).unwrap(), Some(Value::Int(55)));
+    }
+}
@@ -1,17 +1,32 @@
-extern crate regex;
+// This demo only walks a slice of what gets parsed; the rest of the class
+// file's data model (constant pool variants, attribute tables, etc.) is
+// kept in full for callers who need it, not all of it read here yet.
+#![allow(dead_code)]
 
+mod bytecode;
 mod class;
+mod heap_area;
+mod jvm;
+mod stackframe;
 
 use std::path::Path;
 
-fn main() {
+fn main() -> Result<(), class::Error> {
 
     let path = Path::new("examples/Return55.class");
-    let mut class_file = class::ClassFile::new(&path);
-    let class = class_file.read_class();
-    println!("{}", class.this_class_name());
+    let mut class_file = class::ClassFile::new(path)?;
+    let class = class_file.read_class()?;
+    println!("{}", class.this_class_name()?);
+    println!("{:#?}", class.access_flags());
+    if let Some(main) = class.field_or_method_by_name("main") {
+        println!("{:#?}", main.method_access_flags());
+    }
     println!("{:#?}", class);
-    println!("{:#?}", class.main_func_code().unwrap());
+    println!("{:#?}", class.main_func_code()?);
+    println!("{:#?}", class.disassemble_method("main")?);
+    println!("{:#?}", jvm::execute(&class, "main", &[])?);
 
-    println!("{:#?}", class::read_type("(IZI)I"));
+    println!("{:#?}", class::read_type("(IZI)I")?);
+
+    Ok(())
 }
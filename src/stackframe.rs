@@ -0,0 +1,50 @@
+use super::class::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Reference(usize)
+}
+
+pub struct StackFrame {
+    stack: 	Vec<Value>,
+    locals: 	Vec<Option<Value>>,
+    max_stack: 	usize
+}
+
+impl StackFrame {
+
+    pub fn new(max_stack: u16, max_locals: u16) -> StackFrame {
+        StackFrame {
+            stack: 	Vec::with_capacity(max_stack as usize),
+            locals: 	vec![None; max_locals as usize],
+            max_stack: 	max_stack as usize
+        }
+    }
+
+    pub fn push(&mut self, value: Value) -> Result<(), Error> {
+        if self.stack.len() >= self.max_stack {
+            return Err(Error::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Value, Error> {
+        self.stack.pop().ok_or(Error::StackUnderflow)
+    }
+
+    pub fn set_local(&mut self, index: usize, value: Value) -> Result<(), Error> {
+        let slot = self.locals.get_mut(index).ok_or(Error::BadLocal)?;
+        *slot = Some(value);
+        Ok(())
+    }
+
+    pub fn get_local(&self, index: usize) -> Result<Value, Error> {
+        self.locals.get(index).and_then(|v| *v).ok_or(Error::BadLocal)
+    }
+
+}